@@ -2,15 +2,19 @@ use arcstr::ArcStr;
 use oxc::{
   index::IndexVec,
   semantic::{ScopeTree, SymbolTable},
+  span::Span,
 };
 use rolldown_common::{
   side_effects::{DeterminedSideEffects, HookSideEffects},
-  AstScopes, EcmaView, EcmaViewMeta, ImportRecordIdx, ModuleDefFormat, ModuleId, ModuleIdx,
-  ModuleType, RawImportRecord, SymbolRef, SymbolRefDbForModule, TreeshakeOptions,
+  AstScopes, EcmaView, EcmaViewMeta, ExportsKind, ImportRecordIdx, LocalExport, ModuleDefFormat,
+  ModuleId, ModuleIdx, ModuleType, RawImportRecord, StmtInfo, StmtInfos, SymbolRef,
+  SymbolRefDbForModule, TreeshakeOptions,
 };
 use rolldown_ecmascript::EcmaAst;
 use rolldown_error::BuildResult;
-use rolldown_utils::{ecma_script::legitimize_identifier_name, path_ext::PathExt};
+use rolldown_utils::{
+  ecma_script::legitimize_identifier_name, path_ext::PathExt, rustc_hash::FxHashMap,
+};
 use sugar_path::SugarPath;
 
 use crate::{
@@ -46,10 +50,315 @@ fn scan_ast(
     ast.comments(),
   );
   let namespace_object_ref = scanner.namespace_object_ref;
-  let scan_result = scanner.scan(ast.program())?;
+  let mut scan_result = scanner.scan(ast.program())?;
+
+  scan_result.removable_assignments = liveness::find_removable_assignments(&scan_result);
 
   Ok((ast_scopes, scan_result, namespace_object_ref))
 }
+
+/// Backward liveness dataflow over `stmt_infos`, used to spot writes that
+/// are dead even though the statement containing them is otherwise kept
+/// (`side_effects`/`stmt_infos[i].side_effect` only say whether a whole
+/// statement can be dropped). We walk `stmt_infos` in order rather than a
+/// real CFG: it's already a flat, ordered list of top-level effects, and a
+/// statement's `referenced_symbols` summarizes everything it (including
+/// nested function bodies) could read.
+mod liveness {
+  use rolldown_common::{StmtInfoIdx, SymbolRef};
+
+  use crate::ast_scanner::ScanResult;
+
+  /// One `live` bit per (symbol, program point), packed flat rather than
+  /// as one bitset per statement so a whole module's table is a handful
+  /// of words.
+  struct LivenessBits {
+    words: Vec<u64>,
+    symbols_per_point: usize,
+  }
+
+  impl LivenessBits {
+    fn new(points: usize, symbols_per_point: usize) -> Self {
+      let bits = points * symbols_per_point;
+      Self { words: vec![0; bits.div_ceil(u64::BITS as usize)], symbols_per_point }
+    }
+
+    fn index(&self, point: usize, symbol: usize) -> usize {
+      point * self.symbols_per_point + symbol
+    }
+
+    fn set(&mut self, point: usize, symbol: usize) {
+      let bit = self.index(point, symbol);
+      self.words[bit / 64] |= 1 << (bit % 64);
+    }
+
+    fn get(&self, point: usize, symbol: usize) -> bool {
+      let bit = self.index(point, symbol);
+      self.words[bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    fn clear(&mut self, point: usize, symbol: usize) {
+      let bit = self.index(point, symbol);
+      self.words[bit / 64] &= !(1 << (bit % 64));
+    }
+  }
+
+  /// Returns the ids of statements whose declared symbols are *all*
+  /// provably dead writes: none of them is live after the write (nothing
+  /// reads it before it's written again, and it doesn't escape the
+  /// module), and the statement has no side effect worth preserving. A
+  /// statement that declares several symbols (e.g. `let a = 1, b = 2;`) is
+  /// only reported if every one of them is dead — the tree-shaker deletes
+  /// `stmt_infos` wholesale, so reporting a statement where only one
+  /// declared symbol is dead would also delete the live ones.
+  pub(super) fn find_removable_assignments(scan_result: &ScanResult) -> Vec<StmtInfoIdx> {
+    // `eval` can observe and rewrite arbitrary bindings by name, and a
+    // star-export re-exposes every top-level binding to importers, so
+    // neither case gives us a closed set of "things nothing outside the
+    // module can see" to reason about.
+    if scan_result.has_eval || scan_result.has_star_exports {
+      return vec![];
+    }
+
+    let stmt_infos = &scan_result.stmt_infos;
+    let symbol_count = scan_result.symbol_ref_db.len();
+    if symbol_count == 0 || stmt_infos.is_empty() {
+      return vec![];
+    }
+
+    let is_exported = |symbol: SymbolRef| -> bool {
+      scan_result.named_exports.values().any(|export| export.referenced == symbol)
+        || scan_result.default_export_ref == Some(symbol)
+    };
+
+    let exit_point = stmt_infos.len();
+    let mut bits = LivenessBits::new(exit_point + 1, symbol_count);
+    // Exported symbols escape the module, so they're live at the exit point.
+    for (idx, symbol) in scan_result.symbol_ref_db.symbols_enumerated() {
+      if is_exported(symbol) {
+        bits.set(exit_point, idx.index());
+      }
+    }
+    // A closure can be invoked at any later time, including after the
+    // module finishes evaluating, so whatever it captures must be treated
+    // as live at the exit point, like an export, not only as a
+    // point-local read. We can't cheaply tell "this declares a closure"
+    // from "this declares a plain binding whose initializer reads other
+    // symbols" here, so we take the conservative reading for both: the
+    // references are seeded here and also read at their statement below.
+    for stmt_info in stmt_infos.iter() {
+      if stmt_info.declared_symbols.is_empty() {
+        continue;
+      }
+      for &symbol in &stmt_info.referenced_symbols {
+        bits.set(exit_point, symbol.index());
+      }
+    }
+
+    let mut removable = vec![];
+    // Backward pass: live-after at statement i is live-before at i + 1.
+    for (stmt_idx, stmt_info) in stmt_infos.iter_enumerated().rev() {
+      let point = stmt_idx.index();
+      let live_after = point + 1;
+
+      // Live-before starts as live-after; writes kill, reads gen.
+      for sym in 0..symbol_count {
+        if bits.get(live_after, sym) {
+          bits.set(point, sym);
+        }
+      }
+
+      // Every statement reads its references at this point, including one
+      // that declares a symbol: its initializer may read synchronously
+      // (`let y = x;`), on top of the captured-symbol seeding above.
+      for &symbol in &stmt_info.referenced_symbols {
+        bits.set(point, symbol.index());
+      }
+
+      let mut all_declared_dead = !stmt_info.declared_symbols.is_empty();
+      for &symbol in &stmt_info.declared_symbols {
+        if bits.get(point, symbol.index()) {
+          all_declared_dead = false;
+        }
+        if !stmt_info.referenced_symbols.contains(&symbol) {
+          // This write fully overwrites the symbol, so it shadows whatever
+          // liveness was propagated from later in the module: an earlier
+          // write to the same symbol can't be kept alive by a read that
+          // this write makes unobservable.
+          bits.clear(point, symbol.index());
+        }
+      }
+      if all_declared_dead && !stmt_info.side_effect {
+        removable.push(stmt_idx);
+      }
+    }
+
+    removable
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use oxc::span::Span;
+    use rolldown_common::{LocalExport, ModuleIdx, StmtInfo, StmtInfos, SymbolRefDbForModule};
+    use rolldown_utils::rustc_hash::FxHashMap;
+
+    use super::find_removable_assignments;
+    use crate::ast_scanner::ScanResult;
+
+    /// `let x = 1; x = 2; console.log(x);` — the first write to `x` is
+    /// fully overwritten by the second before anything reads it, so only
+    /// the first write should come back as removable.
+    #[test]
+    fn overwritten_write_is_removable() {
+      let mut symbol_ref_db = SymbolRefDbForModule::new(ModuleIdx::new(0));
+      let x = symbol_ref_db.create_symbol_ref("x");
+
+      let mut stmt_infos = StmtInfos::default();
+      stmt_infos.push(StmtInfo {
+        declared_symbols: vec![x],
+        referenced_symbols: vec![],
+        side_effect: false,
+        ..StmtInfo::default()
+      });
+      stmt_infos.push(StmtInfo {
+        declared_symbols: vec![x],
+        referenced_symbols: vec![],
+        side_effect: false,
+        ..StmtInfo::default()
+      });
+      stmt_infos.push(StmtInfo {
+        declared_symbols: vec![],
+        referenced_symbols: vec![x],
+        side_effect: true,
+        ..StmtInfo::default()
+      });
+
+      let scan_result = ScanResult { symbol_ref_db, stmt_infos, ..ScanResult::default() };
+      let removable = find_removable_assignments(&scan_result);
+
+      assert_eq!(removable, vec![0.into()]);
+    }
+
+    /// `let a = 1, b = 2; console.log(a);` as a single `StmtInfo` — `b` is
+    /// dead but `a` is read afterwards, so the statement must not be
+    /// reported (reporting it would delete `a`'s initializer too).
+    #[test]
+    fn statement_with_one_live_and_one_dead_declarator_is_kept() {
+      let mut symbol_ref_db = SymbolRefDbForModule::new(ModuleIdx::new(0));
+      let a = symbol_ref_db.create_symbol_ref("a");
+      let b = symbol_ref_db.create_symbol_ref("b");
+
+      let mut stmt_infos = StmtInfos::default();
+      stmt_infos.push(StmtInfo {
+        declared_symbols: vec![a, b],
+        referenced_symbols: vec![],
+        side_effect: false,
+        ..StmtInfo::default()
+      });
+      stmt_infos.push(StmtInfo {
+        declared_symbols: vec![],
+        referenced_symbols: vec![a],
+        side_effect: true,
+        ..StmtInfo::default()
+      });
+
+      let scan_result = ScanResult { symbol_ref_db, stmt_infos, ..ScanResult::default() };
+      let removable = find_removable_assignments(&scan_result);
+
+      assert!(removable.is_empty());
+    }
+
+    /// `let x = 1; function getX() { return x; } x = 2; export { getX };`
+    /// — `getX` escapes the module, so whatever it captures (`x`) has to be
+    /// treated as live forever, even though nothing in `stmt_infos` order
+    /// textually reads `x` again after the second write. `getX`'s reference
+    /// to `x` also counts as a read at its own statement (the pass can't
+    /// tell it from `let y = x;`), so the first write is kept as well.
+    #[test]
+    fn closure_capture_keeps_symbol_live() {
+      let mut symbol_ref_db = SymbolRefDbForModule::new(ModuleIdx::new(0));
+      let x = symbol_ref_db.create_symbol_ref("x");
+      let get_x = symbol_ref_db.create_symbol_ref("getX");
+
+      let mut stmt_infos = StmtInfos::default();
+      stmt_infos.push(StmtInfo {
+        declared_symbols: vec![x],
+        referenced_symbols: vec![],
+        side_effect: false,
+        ..StmtInfo::default()
+      });
+      stmt_infos.push(StmtInfo {
+        declared_symbols: vec![get_x],
+        referenced_symbols: vec![x],
+        side_effect: false,
+        ..StmtInfo::default()
+      });
+      stmt_infos.push(StmtInfo {
+        declared_symbols: vec![x],
+        referenced_symbols: vec![],
+        side_effect: false,
+        ..StmtInfo::default()
+      });
+
+      let mut named_exports = FxHashMap::default();
+      named_exports.insert("getX".into(), LocalExport { referenced: get_x, span: Span::default() });
+
+      let scan_result =
+        ScanResult { symbol_ref_db, stmt_infos, named_exports, ..ScanResult::default() };
+      let removable = find_removable_assignments(&scan_result);
+
+      assert_eq!(removable, Vec::<rolldown_common::StmtInfoIdx>::new());
+    }
+
+    #[test]
+    fn has_eval_disables_the_pass() {
+      let mut symbol_ref_db = SymbolRefDbForModule::new(ModuleIdx::new(0));
+      let x = symbol_ref_db.create_symbol_ref("x");
+
+      let mut stmt_infos = StmtInfos::default();
+      stmt_infos.push(StmtInfo {
+        declared_symbols: vec![x],
+        referenced_symbols: vec![],
+        side_effect: false,
+        ..StmtInfo::default()
+      });
+      stmt_infos.push(StmtInfo {
+        declared_symbols: vec![x],
+        referenced_symbols: vec![],
+        side_effect: false,
+        ..StmtInfo::default()
+      });
+
+      let scan_result =
+        ScanResult { symbol_ref_db, stmt_infos, has_eval: true, ..ScanResult::default() };
+      assert!(find_removable_assignments(&scan_result).is_empty());
+    }
+
+    #[test]
+    fn has_star_exports_disables_the_pass() {
+      let mut symbol_ref_db = SymbolRefDbForModule::new(ModuleIdx::new(0));
+      let x = symbol_ref_db.create_symbol_ref("x");
+
+      let mut stmt_infos = StmtInfos::default();
+      stmt_infos.push(StmtInfo {
+        declared_symbols: vec![x],
+        referenced_symbols: vec![],
+        side_effect: false,
+        ..StmtInfo::default()
+      });
+      stmt_infos.push(StmtInfo {
+        declared_symbols: vec![x],
+        referenced_symbols: vec![],
+        side_effect: false,
+        ..StmtInfo::default()
+      });
+
+      let scan_result =
+        ScanResult { symbol_ref_db, stmt_infos, has_star_exports: true, ..ScanResult::default() };
+      assert!(find_removable_assignments(&scan_result).is_empty());
+    }
+  }
+}
 pub struct CreateEcmaViewReturn {
   pub view: EcmaView,
   pub raw_import_records: IndexVec<ImportRecordIdx, RawImportRecord>,
@@ -104,6 +413,7 @@ pub async fn create_ecma_view<'any>(
     symbol_ref_db,
     self_referenced_class_decl_symbol_ids,
     has_star_exports,
+    removable_assignments,
   } = scan_result;
   if !errors.is_empty() {
     return Err(errors.into());
@@ -182,6 +492,7 @@ pub async fn create_ecma_view<'any>(
     side_effects,
     ast_usage,
     self_referenced_class_decl_symbol_ids,
+    removable_assignments,
     meta: {
       let mut meta = EcmaViewMeta::default();
       meta.set_included(false);
@@ -195,3 +506,203 @@ pub async fn create_ecma_view<'any>(
 
   Ok(CreateEcmaViewReturn { view, raw_import_records: import_records, ast, symbols: symbol_ref_db })
 }
+
+/// Unlike a scanned export, there's no declaration in source text to point
+/// at, so the only thing a plugin supplies is the exported identifier.
+pub struct SyntheticNamedExportDef {
+  pub exported_name: ArcStr,
+}
+
+/// Describes a module whose shape is declared up front by a plugin instead
+/// of discovered by parsing source text: a fixed list of export names plus
+/// an evaluation step, not a parse result (the synthetic-module pattern
+/// used by JS engines). Typical producers wrap host data as an ESM module
+/// without hand-writing (and then re-parsing) a source string: WASM
+/// instance exports, a generated constant table, a remote manifest, etc.
+pub struct SyntheticModuleDef {
+  pub named_exports: Vec<SyntheticNamedExportDef>,
+  pub has_default_export: bool,
+  /// Executed at evaluation/render time to populate the bindings declared
+  /// above. Never fed to [`parse_to_ecma_ast`] or [`scan_ast`] — none of
+  /// its bindings are analyzed as symbols.
+  pub init_code: ArcStr,
+}
+
+/// Everything a [`SyntheticModuleDef`] contributes to an [`EcmaView`]
+/// besides the namespace object symbol and the ast itself.
+struct SynthesizedExports {
+  default_export_ref: Option<SymbolRef>,
+  named_exports: FxHashMap<ArcStr, LocalExport>,
+  stmt_infos: StmtInfos,
+}
+
+/// Pulled out of [`create_synthetic_ecma_view`] so it can be exercised
+/// without a full [`CreateModuleContext`]. `stmt_infos` gets one synthetic
+/// entry per export, default included, so the tree-shaker's include-marking
+/// (which operates on `stmt_infos`) has something to mark live when that
+/// export is actually used.
+fn synthesize_exports(
+  def: &SyntheticModuleDef,
+  repr_name: &str,
+  namespace_object_ref: SymbolRef,
+  symbol_ref_db: &mut SymbolRefDbForModule,
+) -> SynthesizedExports {
+  let default_export_ref = def
+    .has_default_export
+    .then(|| symbol_ref_db.create_symbol_ref(&format!("{repr_name}_default")));
+
+  let mut named_exports = FxHashMap::default();
+  let mut stmt_infos = StmtInfos::default();
+  // Slot 0 is reserved for the namespace statement, same as scanned
+  // modules, so `import * as ns` can be include-marked against it.
+  stmt_infos.push(StmtInfo {
+    declared_symbols: vec![namespace_object_ref],
+    referenced_symbols: vec![],
+    side_effect: false,
+    ..StmtInfo::default()
+  });
+
+  for export in &def.named_exports {
+    let symbol_ref = symbol_ref_db.create_symbol_ref(&export.exported_name);
+    named_exports.insert(
+      export.exported_name.clone(),
+      LocalExport { referenced: symbol_ref, span: Span::default() },
+    );
+    stmt_infos.push(StmtInfo {
+      declared_symbols: vec![symbol_ref],
+      referenced_symbols: vec![],
+      side_effect: false,
+      ..StmtInfo::default()
+    });
+  }
+
+  if let Some(default_export_ref) = default_export_ref {
+    stmt_infos.push(StmtInfo {
+      declared_symbols: vec![default_export_ref],
+      referenced_symbols: vec![],
+      side_effect: false,
+      ..StmtInfo::default()
+    });
+  }
+
+  SynthesizedExports { default_export_ref, named_exports, stmt_infos }
+}
+
+/// Builds a [`CreateEcmaViewReturn`] for a [`SyntheticModuleDef`] without
+/// running [`parse_to_ecma_ast`] or [`scan_ast`]: the export list is
+/// authoritative and every exported binding is allocated directly in
+/// `symbol_ref_db`, the same database scanned modules register their
+/// symbols in, so linking and renaming treat synthetic exports exactly
+/// like scanned ones.
+pub async fn create_synthetic_ecma_view(
+  ctx: &mut CreateModuleContext<'_>,
+  args: CreateModuleViewArgs,
+  def: SyntheticModuleDef,
+) -> BuildResult<CreateEcmaViewReturn> {
+  let id = ModuleId::new(ArcStr::clone(&ctx.resolved_id.id));
+  let repr_name = id.as_path().representative_file_name();
+  let repr_name = legitimize_identifier_name(&repr_name);
+
+  let mut symbol_ref_db = SymbolRefDbForModule::new(ctx.module_index);
+
+  let namespace_object_ref = symbol_ref_db.create_symbol_ref(&format!("{repr_name}_ns_exports"));
+  let SynthesizedExports { default_export_ref, named_exports, stmt_infos } =
+    synthesize_exports(&def, &repr_name, namespace_object_ref, &mut symbol_ref_db);
+
+  // The init template is only ever emitted, never analyzed, so it does not
+  // go through the plugin-facing `parse_to_ecma_ast` pipeline (no defines,
+  // no transforms) or `scan_ast` (no symbols are discovered from it).
+  let ast = EcmaAst::from_snippet(&id, def.init_code.clone())?;
+
+  let view = EcmaView {
+    source: def.init_code,
+    ecma_ast_idx: None,
+    named_imports: FxHashMap::default(),
+    named_exports,
+    stmt_infos,
+    imports: FxHashMap::default(),
+    default_export_ref,
+    scope: AstScopes::default(),
+    exports_kind: ExportsKind::Esm,
+    namespace_object_ref,
+    def_format: ModuleDefFormat::EsmMjs,
+    sourcemap_chain: args.sourcemap_chain,
+    import_records: IndexVec::default(),
+    importers: vec![],
+    dynamic_importers: vec![],
+    imported_ids: vec![],
+    dynamically_imported_ids: vec![],
+    side_effects: DeterminedSideEffects::UserDefined(false),
+    ast_usage: Default::default(),
+    self_referenced_class_decl_symbol_ids: vec![],
+    // The liveness pass only runs in `scan_ast`, which synthetic modules
+    // skip, so no statement is reported removable.
+    removable_assignments: vec![],
+    meta: {
+      let mut meta = EcmaViewMeta::default();
+      meta.set_included(false);
+      meta
+    },
+    mutations: vec![],
+  };
+
+  Ok(CreateEcmaViewReturn {
+    view,
+    raw_import_records: IndexVec::default(),
+    ast,
+    symbols: symbol_ref_db,
+  })
+}
+
+#[cfg(test)]
+mod synthetic_module_tests {
+  use rolldown_common::ModuleIdx;
+
+  use super::{
+    synthesize_exports, SymbolRefDbForModule, SyntheticModuleDef, SyntheticNamedExportDef,
+  };
+
+  #[test]
+  fn one_stmt_info_per_named_export() {
+    let mut symbol_ref_db = SymbolRefDbForModule::new(ModuleIdx::new(0));
+    let ns = symbol_ref_db.create_symbol_ref("mod_ns_exports");
+    let def = SyntheticModuleDef {
+      named_exports: vec![
+        SyntheticNamedExportDef { exported_name: "a".into() },
+        SyntheticNamedExportDef { exported_name: "b".into() },
+      ],
+      has_default_export: false,
+      init_code: "".into(),
+    };
+
+    let exports = synthesize_exports(&def, "mod", ns, &mut symbol_ref_db);
+
+    assert_eq!(exports.named_exports.len(), 2);
+    // Namespace slot + one slot per named export.
+    assert_eq!(exports.stmt_infos.len(), 3);
+    assert_eq!(exports.stmt_infos[0.into()].declared_symbols, vec![ns]);
+    assert!(exports.default_export_ref.is_none());
+  }
+
+  #[test]
+  fn default_export_gets_its_own_stmt_info() {
+    let mut symbol_ref_db = SymbolRefDbForModule::new(ModuleIdx::new(0));
+    let ns = symbol_ref_db.create_symbol_ref("mod_ns_exports");
+    let def = SyntheticModuleDef {
+      named_exports: vec![SyntheticNamedExportDef { exported_name: "a".into() }],
+      has_default_export: true,
+      init_code: "".into(),
+    };
+
+    let exports = synthesize_exports(&def, "mod", ns, &mut symbol_ref_db);
+
+    assert!(exports.default_export_ref.is_some());
+    // Namespace slot + one named export + one default export.
+    assert_eq!(exports.stmt_infos.len(), 3);
+    let default_export_ref = exports.default_export_ref.unwrap();
+    assert!(exports
+      .stmt_infos
+      .iter()
+      .any(|stmt_info| stmt_info.declared_symbols == vec![default_export_ref]));
+  }
+}